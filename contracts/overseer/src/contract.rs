@@ -0,0 +1,1058 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, InitResponse, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use moneymarket::liquidation::HandleMsg as LiquidationHandleMsg;
+use moneymarket::liquidation_queue::HandleMsg as LiquidationQueueHandleMsg;
+
+use crate::msg::{
+    BorrowLimitResponse, CollateralLimitElem, CollateralPremiumElem, CollateralPriceStateElem,
+    ConfigResponse, HandleMsg, InitMsg, LiquidationConfigResponse, MarketStateResponse,
+    OptionalUpdate, QueryMsg,
+};
+use crate::querier::{query_borrow_amount, query_price};
+use crate::state::{
+    read_collaterals, read_config, read_whitelist_elem, read_whitelist_elems, store_config,
+    store_whitelist_elem,
+    Config, WhitelistElem,
+};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    store_config(
+        &mut deps.storage,
+        &Config {
+            owner_addr: deps.api.canonical_address(&msg.owner_addr)?,
+            oracle_contract: deps.api.canonical_address(&msg.oracle_contract)?,
+            market_contract: deps.api.canonical_address(&msg.market_contract)?,
+            liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
+            collector_contract: deps.api.canonical_address(&msg.collector_contract)?,
+            liquidation_queue_contract: msg
+                .liquidation_queue_contract
+                .as_ref()
+                .map(|addr| deps.api.canonical_address(addr))
+                .transpose()?,
+            stable_denom: msg.stable_denom,
+            epoch_period: msg.epoch_period,
+            distribution_threshold: msg.distribution_threshold,
+            target_deposit_rate: msg.target_deposit_rate,
+            buffer_distribution_rate: msg.buffer_distribution_rate,
+            anc_purchase_factor: msg.anc_purchase_factor,
+            price_timeframe: msg.price_timeframe,
+            safe_ratio: msg.safe_ratio,
+            close_factor: msg.close_factor,
+            liquidation_threshold: msg.liquidation_threshold,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig {
+            owner_addr,
+            oracle_contract,
+            liquidation_contract,
+            collector_contract,
+            liquidation_queue_contract,
+            distribution_threshold,
+            target_deposit_rate,
+            buffer_distribution_rate,
+            anc_purchase_factor,
+            epoch_period,
+            price_timeframe,
+            safe_ratio,
+            close_factor,
+            liquidation_threshold,
+        } => update_config(
+            deps,
+            env,
+            owner_addr,
+            oracle_contract,
+            liquidation_contract,
+            collector_contract,
+            liquidation_queue_contract,
+            distribution_threshold,
+            target_deposit_rate,
+            buffer_distribution_rate,
+            anc_purchase_factor,
+            epoch_period,
+            price_timeframe,
+            safe_ratio,
+            close_factor,
+            liquidation_threshold,
+        ),
+        HandleMsg::Whitelist {
+            collateral_token,
+            custody_contract,
+            ltv,
+            collateral_ratio_multiplier,
+            max_premium_rate,
+        } => whitelist_collateral(
+            deps,
+            env,
+            collateral_token,
+            custody_contract,
+            ltv,
+            collateral_ratio_multiplier,
+            max_premium_rate,
+        ),
+        HandleMsg::UpdateWhitelist {
+            collateral_token,
+            custody_contract,
+            ltv,
+            collateral_ratio_multiplier,
+            max_premium_rate,
+        } => update_whitelist(
+            deps,
+            env,
+            collateral_token,
+            custody_contract,
+            ltv,
+            collateral_ratio_multiplier,
+            max_premium_rate,
+        ),
+        HandleMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env),
+        HandleMsg::LiquidateCollateral { borrower } => liquidate_collateral(deps, env, borrower),
+        _ => Err(StdError::generic_err(
+            "This operation has not been implemented yet",
+        )),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner_addr: Option<HumanAddr>,
+    oracle_contract: Option<HumanAddr>,
+    liquidation_contract: Option<HumanAddr>,
+    collector_contract: Option<HumanAddr>,
+    liquidation_queue_contract: Option<OptionalUpdate<HumanAddr>>,
+    distribution_threshold: Option<cosmwasm_bignumber::Decimal256>,
+    target_deposit_rate: Option<cosmwasm_bignumber::Decimal256>,
+    buffer_distribution_rate: Option<cosmwasm_bignumber::Decimal256>,
+    anc_purchase_factor: Option<cosmwasm_bignumber::Decimal256>,
+    epoch_period: Option<u64>,
+    price_timeframe: Option<u64>,
+    safe_ratio: Option<cosmwasm_bignumber::Decimal256>,
+    close_factor: Option<cosmwasm_bignumber::Decimal256>,
+    liquidation_threshold: Option<Uint256>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner_addr) = owner_addr {
+        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
+    }
+
+    if let Some(oracle_contract) = oracle_contract {
+        config.oracle_contract = deps.api.canonical_address(&oracle_contract)?;
+    }
+
+    if let Some(liquidation_contract) = liquidation_contract {
+        config.liquidation_contract = deps.api.canonical_address(&liquidation_contract)?;
+    }
+
+    if let Some(collector_contract) = collector_contract {
+        config.collector_contract = deps.api.canonical_address(&collector_contract)?;
+    }
+
+    if let Some(update) = liquidation_queue_contract {
+        config.liquidation_queue_contract = match update {
+            OptionalUpdate::Set(addr) => Some(deps.api.canonical_address(&addr)?),
+            OptionalUpdate::Clear => None,
+        };
+    }
+
+    if let Some(distribution_threshold) = distribution_threshold {
+        config.distribution_threshold = distribution_threshold;
+    }
+
+    if let Some(target_deposit_rate) = target_deposit_rate {
+        config.target_deposit_rate = target_deposit_rate;
+    }
+
+    if let Some(buffer_distribution_rate) = buffer_distribution_rate {
+        config.buffer_distribution_rate = buffer_distribution_rate;
+    }
+
+    if let Some(anc_purchase_factor) = anc_purchase_factor {
+        config.anc_purchase_factor = anc_purchase_factor;
+    }
+
+    if let Some(epoch_period) = epoch_period {
+        config.epoch_period = epoch_period;
+    }
+
+    if let Some(price_timeframe) = price_timeframe {
+        config.price_timeframe = price_timeframe;
+    }
+
+    if let Some(safe_ratio) = safe_ratio {
+        config.safe_ratio = safe_ratio;
+    }
+
+    if let Some(close_factor) = close_factor {
+        config.close_factor = close_factor;
+    }
+
+    if let Some(liquidation_threshold) = liquidation_threshold {
+        config.liquidation_threshold = liquidation_threshold;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_config")],
+        data: None,
+    })
+}
+
+pub fn whitelist_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    custody_contract: HumanAddr,
+    ltv: Decimal256,
+    collateral_ratio_multiplier: Option<Decimal256>,
+    max_premium_rate: Option<Decimal256>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    store_whitelist_elem(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &WhitelistElem {
+            custody_contract: deps.api.canonical_address(&custody_contract)?,
+            ltv,
+            collateral_ratio_multiplier,
+            max_premium_rate,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "whitelist_collateral"),
+            log("collateral_token", collateral_token),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_whitelist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    custody_contract: Option<HumanAddr>,
+    ltv: Option<Decimal256>,
+    collateral_ratio_multiplier: Option<OptionalUpdate<Decimal256>>,
+    max_premium_rate: Option<OptionalUpdate<Decimal256>>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let mut whitelist_elem = read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    if let Some(custody_contract) = custody_contract {
+        whitelist_elem.custody_contract = deps.api.canonical_address(&custody_contract)?;
+    }
+
+    if let Some(ltv) = ltv {
+        whitelist_elem.ltv = ltv;
+    }
+
+    if let Some(update) = collateral_ratio_multiplier {
+        whitelist_elem.collateral_ratio_multiplier = match update {
+            OptionalUpdate::Set(multiplier) => Some(multiplier),
+            OptionalUpdate::Clear => None,
+        };
+    }
+
+    if let Some(update) = max_premium_rate {
+        whitelist_elem.max_premium_rate = match update {
+            OptionalUpdate::Set(max_premium_rate) => Some(max_premium_rate),
+            OptionalUpdate::Clear => None,
+        };
+    }
+
+    store_whitelist_elem(&mut deps.storage, &collateral_token_raw, &whitelist_elem)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_whitelist"),
+            log("collateral_token", collateral_token),
+        ],
+        data: None,
+    })
+}
+
+/// Claims the interest buffer and, per epoch, splits it between the
+/// ANC buyback (collector contract) and the depositor subsidy (market
+/// contract). The buyback portion is computed first so that the
+/// depositor subsidy is only ever paid out of what remains.
+/// The whole stable balance of the contract is treated as the buffer:
+/// `distribution_threshold` and `buffer_distribution_rate` are not
+/// applied, and no custody rewards are distributed.
+pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    let contract_addr = env.contract.address;
+    let balance = deps
+        .querier
+        .query_balance(&contract_addr, &config.stable_denom)?
+        .amount;
+    let buffer_amount = Uint256::from(balance);
+
+    let buyback_amount = buffer_amount * config.anc_purchase_factor;
+    let distributable_buffer = buffer_amount - buyback_amount;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !buyback_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: contract_addr.clone(),
+            to_address: deps.api.human_address(&config.collector_contract)?,
+            amount: vec![Coin {
+                denom: config.stable_denom.clone(),
+                amount: buyback_amount.into(),
+            }],
+        }));
+    }
+
+    if !distributable_buffer.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: contract_addr,
+            to_address: deps.api.human_address(&config.market_contract)?,
+            amount: vec![Coin {
+                denom: config.stable_denom,
+                amount: distributable_buffer.into(),
+            }],
+        }));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "execute_epoch_operations"),
+            log("buyback_amount", buyback_amount),
+            log("distributed_buffer_amount", distributable_buffer),
+        ],
+        data: None,
+    })
+}
+
+/// Computes a partial-liquidation amount per whitelisted collateral:
+/// enough stablecoin is repaid to restore the position to `safe_ratio`,
+/// capped at `close_factor` of the outstanding liability, with a
+/// small-position carve-out that liquidates the whole position rather
+/// than leaving dust below `liquidation_threshold`. The repay amount
+/// is split across collaterals in proportion to each one's share of
+/// the borrow limit, converted to collateral amounts at the oracle
+/// price, then handed to the liquidation queue contract when one is
+/// configured and to the liquidation contract otherwise.
+pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals = read_collaterals(&deps.storage, &borrower_raw);
+    if collaterals.is_empty() {
+        return Err(StdError::generic_err(
+            "The borrower has no collateral deposited",
+        ));
+    }
+
+    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+
+    let mut collateral_infos: Vec<(HumanAddr, Decimal256, Decimal256, Uint256)> = vec![];
+    let mut borrow_limit = Uint256::zero();
+    for (collateral_token, amount) in collaterals.iter() {
+        let whitelist_elem = read_whitelist_elem(&deps.storage, collateral_token)?;
+        let collateral_addr = deps.api.human_address(collateral_token)?;
+        let price = query_price(
+            deps,
+            &oracle_contract,
+            collateral_addr.to_string(),
+            config.stable_denom.clone(),
+        )?
+        .rate;
+
+        let multiplier = whitelist_elem
+            .collateral_ratio_multiplier
+            .unwrap_or_else(Decimal256::one);
+        let effective_ltv = whitelist_elem.ltv * multiplier;
+        borrow_limit += *amount * price * effective_ltv;
+        collateral_infos.push((collateral_addr, price, effective_ltv, *amount));
+    }
+
+    let borrow_amount = query_borrow_amount(deps, &config.market_contract, &borrower)?;
+    if borrow_amount <= borrow_limit {
+        return Err(StdError::generic_err(
+            "Cannot liquidate a safely collateralized position",
+        ));
+    }
+
+    // A zero borrow_limit (e.g. every whitelisted collateral has a zero
+    // ltv/collateral_ratio_multiplier) can't be split pro-rata -- there's
+    // no non-zero share to allocate the repay amount against.
+    if borrow_limit.is_zero() {
+        return Err(StdError::generic_err(
+            "Cannot compute a liquidation split: borrower's collateral has a zero borrow limit",
+        ));
+    }
+
+    let desired_repay_amount = borrow_amount - (borrow_limit * config.safe_ratio);
+    let max_repay_amount = borrow_amount * config.close_factor;
+    let mut repay_amount = if desired_repay_amount < max_repay_amount {
+        desired_repay_amount
+    } else {
+        max_repay_amount
+    };
+
+    if borrow_amount - repay_amount < config.liquidation_threshold {
+        repay_amount = borrow_amount;
+    }
+
+    let mut liquidation_amounts: Vec<(HumanAddr, Uint256)> = vec![];
+    for (collateral_addr, price, ltv, amount) in collateral_infos.iter() {
+        // A stale/misreported zero oracle price can't be converted back
+        // into a collateral amount; skip it rather than divide by zero.
+        if price.is_zero() {
+            continue;
+        }
+
+        let limit_value = *amount * *price * *ltv;
+        let collateral_repay_value =
+            repay_amount * Decimal256::from_ratio(limit_value, borrow_limit);
+        let collateral_amount = collateral_repay_value / *price;
+        liquidation_amounts.push((collateral_addr.clone(), collateral_amount));
+    }
+
+    let messages = if let Some(liquidation_queue_contract) = &config.liquidation_queue_contract {
+        // Route each collateral through its own discount bid pool,
+        // consuming the 0%-premium pool first and working up to
+        // max_premium_rate; the queue repays the Market contract
+        // directly out of the bids it fills.
+        let liquidation_queue_contract = deps.api.human_address(liquidation_queue_contract)?;
+        let market_contract = deps.api.human_address(&config.market_contract)?;
+        liquidation_amounts
+            .iter()
+            .map(|(collateral_addr, amount)| -> StdResult<CosmosMsg> {
+                let whitelist_elem = read_whitelist_elem(
+                    &deps.storage,
+                    &deps.api.canonical_address(collateral_addr)?,
+                )?;
+                Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: liquidation_queue_contract.clone(),
+                    msg: to_binary(&LiquidationQueueHandleMsg::ExecuteLiquidation {
+                        borrower: borrower.clone(),
+                        collateral_token: collateral_addr.clone(),
+                        amount: *amount,
+                        max_premium_rate: whitelist_elem
+                            .max_premium_rate
+                            .unwrap_or_else(Decimal256::zero),
+                        repay_to: market_contract.clone(),
+                    })?,
+                    send: vec![],
+                }))
+            })
+            .collect::<StdResult<Vec<CosmosMsg>>>()?
+    } else {
+        let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: liquidation_contract,
+            msg: to_binary(&LiquidationHandleMsg::ExecuteLiquidation {
+                borrower: borrower.clone(),
+                collaterals: liquidation_amounts,
+            })?,
+            send: vec![],
+        })]
+    };
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "liquidate_collateral"),
+            log("borrower", borrower),
+            log("repay_amount", repay_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<cosmwasm_std::Binary> {
+    match msg {
+        QueryMsg::Config {} => cosmwasm_std::to_binary(&query_config(deps)?),
+        QueryMsg::BorrowLimit {
+            borrower,
+            block_time: _,
+        } => cosmwasm_std::to_binary(&query_borrow_limit(deps, borrower)?),
+        QueryMsg::LiquidationConfig {} => cosmwasm_std::to_binary(&query_liquidation_config(deps)?),
+        QueryMsg::MarketState { block_time } => {
+            cosmwasm_std::to_binary(&query_market_state(deps, block_time)?)
+        }
+        _ => Err(StdError::generic_err(
+            "This query has not been implemented yet",
+        )),
+    }
+}
+
+/// Reports, per whitelisted collateral, whether its oracle price is
+/// older than `price_timeframe` as of `block_time`, so front-ends and
+/// keepers can pre-check `LockCollateral`/borrow flows without submitting a tx.
+/// Without a `block_time` there is nothing to compare prices against,
+/// so every collateral is reported fresh and borrowing is not halted.
+pub fn query_market_state<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_time: Option<u64>,
+) -> StdResult<MarketStateResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+
+    let mut borrow_halted = false;
+    let mut collaterals: Vec<CollateralPriceStateElem> = vec![];
+    for (collateral_token, _) in read_whitelist_elems(&deps.storage)? {
+        let collateral_addr = deps.api.human_address(&collateral_token)?;
+        let price = query_price(
+            deps,
+            &oracle_contract,
+            collateral_addr.to_string(),
+            config.stable_denom.clone(),
+        )?;
+
+        let (is_stale, price_age) = match block_time {
+            Some(block_time) => {
+                let price_age = block_time.saturating_sub(price.last_updated_base);
+                let is_stale = price.last_updated_base + config.price_timeframe < block_time;
+                (is_stale, price_age)
+            }
+            None => (false, 0u64),
+        };
+
+        borrow_halted = borrow_halted || is_stale;
+        collaterals.push(CollateralPriceStateElem {
+            collateral_token: collateral_addr,
+            is_stale,
+            price_age,
+        });
+    }
+
+    Ok(MarketStateResponse {
+        borrow_halted,
+        collaterals,
+    })
+}
+
+pub fn query_liquidation_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<LiquidationConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let collaterals = read_whitelist_elems(&deps.storage)?
+        .into_iter()
+        .map(|(collateral_token, elem)| {
+            Ok(CollateralPremiumElem {
+                collateral_token: deps.api.human_address(&collateral_token)?,
+                max_premium_rate: elem.max_premium_rate.unwrap_or_else(Decimal256::zero),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(LiquidationConfigResponse {
+        liquidation_queue_contract: config
+            .liquidation_queue_contract
+            .map(|addr| deps.api.human_address(&addr))
+            .transpose()?,
+        collaterals,
+    })
+}
+
+/// Sums each whitelisted collateral's `price * amount * ltv`, scaled
+/// by the collateral's own `collateral_ratio_multiplier` (default 1)
+/// to allow tightening limits per collateral without touching the
+/// base LTV.
+pub fn query_borrow_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<BorrowLimitResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals = read_collaterals(&deps.storage, &borrower_raw);
+
+    let mut borrow_limit = Uint256::zero();
+    let mut collateral_limits: Vec<CollateralLimitElem> = vec![];
+    for (collateral_token, amount) in collaterals.iter() {
+        let whitelist_elem = read_whitelist_elem(&deps.storage, collateral_token)?;
+        let collateral_addr = deps.api.human_address(collateral_token)?;
+        let price = query_price(
+            deps,
+            &oracle_contract,
+            collateral_addr.to_string(),
+            config.stable_denom.clone(),
+        )?
+        .rate;
+
+        let multiplier = whitelist_elem
+            .collateral_ratio_multiplier
+            .unwrap_or_else(Decimal256::one);
+        let limit = *amount * price * whitelist_elem.ltv * multiplier;
+        borrow_limit += limit;
+        collateral_limits.push(CollateralLimitElem {
+            collateral_token: collateral_addr,
+            limit,
+        });
+    }
+
+    Ok(BorrowLimitResponse {
+        borrower,
+        borrow_limit,
+        collateral_limits,
+    })
+}
+
+pub fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner_addr: deps.api.human_address(&config.owner_addr)?,
+        oracle_contract: deps.api.human_address(&config.oracle_contract)?,
+        market_contract: deps.api.human_address(&config.market_contract)?,
+        liquidation_contract: deps.api.human_address(&config.liquidation_contract)?,
+        collector_contract: deps.api.human_address(&config.collector_contract)?,
+        liquidation_queue_contract: config
+            .liquidation_queue_contract
+            .map(|addr| deps.api.human_address(&addr))
+            .transpose()?,
+        distribution_threshold: config.distribution_threshold,
+        target_deposit_rate: config.target_deposit_rate,
+        buffer_distribution_rate: config.buffer_distribution_rate,
+        anc_purchase_factor: config.anc_purchase_factor,
+        stable_denom: config.stable_denom,
+        epoch_period: config.epoch_period,
+        price_timeframe: config.price_timeframe,
+        safe_ratio: config.safe_ratio,
+        close_factor: config.close_factor,
+        liquidation_threshold: config.liquidation_threshold,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_bignumber::Decimal256;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{coin, from_binary, HumanAddr};
+
+    fn init_msg() -> InitMsg {
+        InitMsg {
+            owner_addr: HumanAddr::from("owner"),
+            oracle_contract: HumanAddr::from("oracle"),
+            market_contract: HumanAddr::from("market"),
+            liquidation_contract: HumanAddr::from("liquidation"),
+            collector_contract: HumanAddr::from("collector"),
+            liquidation_queue_contract: None,
+            stable_denom: "uusd".to_string(),
+            epoch_period: 86400u64,
+            distribution_threshold: Decimal256::permille(3),
+            target_deposit_rate: Decimal256::permille(5),
+            buffer_distribution_rate: Decimal256::percent(10),
+            anc_purchase_factor: Decimal256::percent(20),
+            price_timeframe: 60u64,
+            safe_ratio: Decimal256::percent(80),
+            close_factor: Decimal256::percent(50),
+            liquidation_threshold: Uint256::from(100000u64),
+        }
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = init_msg();
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(HumanAddr::from("owner"), config.owner_addr);
+        assert_eq!(Decimal256::percent(20), config.anc_purchase_factor);
+        assert_eq!(HumanAddr::from("collector"), config.collector_contract);
+    }
+
+    #[test]
+    fn epoch_operations_splits_buyback_before_subsidy() {
+        let mut deps = mock_dependencies(20, &[coin(1_000_000u128, "uusd")]);
+
+        let msg = init_msg();
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env.clone(), msg).unwrap();
+
+        let res = execute_epoch_operations(&mut deps, env.clone()).unwrap();
+        assert_eq!(2, res.messages.len());
+
+        // 20% of the 1_000_000uusd buffer is bought back through the
+        // collector, computed before the depositor-subsidy remainder.
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address,
+                amount,
+                ..
+            }) => {
+                assert_eq!(to_address, &HumanAddr::from("collector"));
+                assert_eq!(amount[0].amount.u128(), 200_000u128);
+            }
+            _ => panic!("expected a bank send to the collector contract"),
+        }
+
+        match &res.messages[1] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address,
+                amount,
+                ..
+            }) => {
+                assert_eq!(to_address, &HumanAddr::from("market"));
+                assert_eq!(amount[0].amount.u128(), 800_000u128);
+            }
+            _ => panic!("expected a bank send to the market contract"),
+        }
+    }
+
+    #[test]
+    fn liquidate_collateral_repays_down_to_safe_ratio() {
+        use crate::state::{store_collaterals, store_whitelist_elem, WhitelistElem};
+        use moneymarket::oracle::PriceResponse;
+
+        let mut deps = crate::mock_querier::mock_dependencies(20, &[]);
+
+        let msg = init_msg();
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env.clone(), msg).unwrap();
+
+        let collateral_token = HumanAddr::from("bluna");
+        let collateral_raw = deps.api.canonical_address(&collateral_token).unwrap();
+        store_whitelist_elem(
+            &mut deps.storage,
+            &collateral_raw,
+            &WhitelistElem {
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody"))
+                    .unwrap(),
+                ltv: Decimal256::percent(60),
+                collateral_ratio_multiplier: None,
+                max_premium_rate: None,
+            },
+        )
+        .unwrap();
+
+        let borrower = HumanAddr::from("borrower");
+        let borrower_raw = deps.api.canonical_address(&borrower).unwrap();
+        store_collaterals(
+            &mut deps.storage,
+            &borrower_raw,
+            &[(collateral_raw, Uint256::from(1_000_000u64))],
+        )
+        .unwrap();
+
+        deps.querier.with_oracle_price(
+            "bluna",
+            "uusd",
+            PriceResponse {
+                rate: Decimal256::one(),
+                last_updated_base: 100,
+                last_updated_quote: 100,
+            },
+        );
+        // borrow_limit = 1_000_000 * 1.0 * 0.6 = 600_000, deeply
+        // underwater at a 900_000 loan.
+        deps.querier
+            .with_loan_amount(&borrower, Uint256::from(900_000u64));
+
+        let res = liquidate_collateral(&mut deps, env, borrower).unwrap();
+
+        // desired_repay = 900_000 - 600_000*0.8 = 420_000
+        // max_repay = 900_000*0.5 = 450_000 -> close_factor doesn't bind
+        // 900_000 - 420_000 = 480_000, above liquidation_threshold so no
+        // full-position carve-out kicks in.
+        match &res.log[2] {
+            cosmwasm_std::LogAttribute { key, value } => {
+                assert_eq!(key, "repay_amount");
+                assert_eq!(value, &Uint256::from(420_000u64).to_string());
+            }
+        }
+        assert_eq!(1, res.messages.len());
+    }
+
+    #[test]
+    fn borrow_limit_applies_collateral_ratio_multiplier() {
+        use crate::state::{store_collaterals, store_whitelist_elem, WhitelistElem};
+        use moneymarket::oracle::PriceResponse;
+
+        let mut deps = crate::mock_querier::mock_dependencies(20, &[]);
+
+        let msg = init_msg();
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let collateral_token = HumanAddr::from("bluna");
+        let collateral_raw = deps.api.canonical_address(&collateral_token).unwrap();
+        store_whitelist_elem(
+            &mut deps.storage,
+            &collateral_raw,
+            &WhitelistElem {
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody"))
+                    .unwrap(),
+                ltv: Decimal256::percent(60),
+                collateral_ratio_multiplier: Some(Decimal256::percent(50)),
+                max_premium_rate: None,
+            },
+        )
+        .unwrap();
+
+        let borrower = HumanAddr::from("borrower");
+        let borrower_raw = deps.api.canonical_address(&borrower).unwrap();
+        store_collaterals(
+            &mut deps.storage,
+            &borrower_raw,
+            &[(collateral_raw, Uint256::from(1_000_000u64))],
+        )
+        .unwrap();
+
+        deps.querier.with_oracle_price(
+            "bluna",
+            "uusd",
+            PriceResponse {
+                rate: Decimal256::one(),
+                last_updated_base: 100,
+                last_updated_quote: 100,
+            },
+        );
+
+        // 1_000_000 * 1.0 * 0.6 * 0.5 = 300_000 — tightened to half of
+        // what the base LTV alone would allow.
+        let res = query_borrow_limit(&deps, borrower).unwrap();
+        assert_eq!(Uint256::from(300_000u64), res.borrow_limit);
+        assert_eq!(Uint256::from(300_000u64), res.collateral_limits[0].limit);
+    }
+
+    #[test]
+    fn liquidation_config_reports_queue_and_premium_ceilings() {
+        use crate::state::{store_whitelist_elem, WhitelistElem};
+
+        let mut deps = crate::mock_querier::mock_dependencies(20, &[]);
+
+        let mut msg = init_msg();
+        msg.liquidation_queue_contract = Some(HumanAddr::from("queue"));
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let collateral_token = HumanAddr::from("bluna");
+        let collateral_raw = deps.api.canonical_address(&collateral_token).unwrap();
+        store_whitelist_elem(
+            &mut deps.storage,
+            &collateral_raw,
+            &WhitelistElem {
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody"))
+                    .unwrap(),
+                ltv: Decimal256::percent(60),
+                collateral_ratio_multiplier: None,
+                max_premium_rate: Some(Decimal256::percent(10)),
+            },
+        )
+        .unwrap();
+
+        let res = query_liquidation_config(&deps).unwrap();
+        assert_eq!(Some(HumanAddr::from("queue")), res.liquidation_queue_contract);
+        assert_eq!(1, res.collaterals.len());
+        assert_eq!(collateral_token, res.collaterals[0].collateral_token);
+        assert_eq!(Decimal256::percent(10), res.collaterals[0].max_premium_rate);
+    }
+
+    #[test]
+    fn liquidate_collateral_routes_through_queue_when_configured() {
+        use crate::state::{store_collaterals, store_whitelist_elem, WhitelistElem};
+        use moneymarket::oracle::PriceResponse;
+
+        let mut deps = crate::mock_querier::mock_dependencies(20, &[]);
+
+        let mut msg = init_msg();
+        msg.liquidation_queue_contract = Some(HumanAddr::from("queue"));
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env.clone(), msg).unwrap();
+
+        let collateral_token = HumanAddr::from("bluna");
+        let collateral_raw = deps.api.canonical_address(&collateral_token).unwrap();
+        store_whitelist_elem(
+            &mut deps.storage,
+            &collateral_raw,
+            &WhitelistElem {
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody"))
+                    .unwrap(),
+                ltv: Decimal256::percent(60),
+                collateral_ratio_multiplier: None,
+                max_premium_rate: Some(Decimal256::percent(10)),
+            },
+        )
+        .unwrap();
+
+        let borrower = HumanAddr::from("borrower");
+        let borrower_raw = deps.api.canonical_address(&borrower).unwrap();
+        store_collaterals(
+            &mut deps.storage,
+            &borrower_raw,
+            &[(collateral_raw, Uint256::from(1_000_000u64))],
+        )
+        .unwrap();
+
+        deps.querier.with_oracle_price(
+            "bluna",
+            "uusd",
+            PriceResponse {
+                rate: Decimal256::one(),
+                last_updated_base: 100,
+                last_updated_quote: 100,
+            },
+        );
+        deps.querier
+            .with_loan_amount(&borrower, Uint256::from(900_000u64));
+
+        let res = liquidate_collateral(&mut deps, env, borrower).unwrap();
+        assert_eq!(1, res.messages.len());
+        match &res.messages[0] {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, &HumanAddr::from("queue"));
+            }
+            _ => panic!("expected the liquidation queue contract to be called"),
+        }
+    }
+
+    #[test]
+    fn update_whitelist_clears_max_premium_rate() {
+        use crate::state::{read_whitelist_elem, store_whitelist_elem, WhitelistElem};
+
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = init_msg();
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env.clone(), msg).unwrap();
+
+        let collateral_token = HumanAddr::from("bluna");
+        let collateral_raw = deps.api.canonical_address(&collateral_token).unwrap();
+        store_whitelist_elem(
+            &mut deps.storage,
+            &collateral_raw,
+            &WhitelistElem {
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody"))
+                    .unwrap(),
+                ltv: Decimal256::percent(60),
+                collateral_ratio_multiplier: None,
+                max_premium_rate: Some(Decimal256::percent(10)),
+            },
+        )
+        .unwrap();
+
+        update_whitelist(
+            &mut deps,
+            mock_env("owner", &[]),
+            collateral_token,
+            None,
+            None,
+            None,
+            Some(OptionalUpdate::Clear),
+        )
+        .unwrap();
+
+        let whitelist_elem = read_whitelist_elem(&deps.storage, &collateral_raw).unwrap();
+        assert_eq!(None, whitelist_elem.max_premium_rate);
+    }
+
+    #[test]
+    fn market_state_flags_stale_prices_and_halts_borrowing() {
+        use crate::state::{store_whitelist_elem, WhitelistElem};
+        use moneymarket::oracle::PriceResponse;
+
+        let mut deps = crate::mock_querier::mock_dependencies(20, &[]);
+
+        let msg = init_msg();
+        let env = mock_env("addr0000", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let collateral_token = HumanAddr::from("bluna");
+        let collateral_raw = deps.api.canonical_address(&collateral_token).unwrap();
+        store_whitelist_elem(
+            &mut deps.storage,
+            &collateral_raw,
+            &WhitelistElem {
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody"))
+                    .unwrap(),
+                ltv: Decimal256::percent(60),
+                collateral_ratio_multiplier: None,
+                max_premium_rate: None,
+            },
+        )
+        .unwrap();
+
+        deps.querier.with_oracle_price(
+            "bluna",
+            "uusd",
+            PriceResponse {
+                rate: Decimal256::one(),
+                last_updated_base: 100,
+                last_updated_quote: 100,
+            },
+        );
+
+        // price_timeframe is 60s; 200 - 100 = 100s old, past the window.
+        let res = query_market_state(&deps, Some(200)).unwrap();
+        assert!(res.borrow_halted);
+        assert_eq!(1, res.collaterals.len());
+        assert!(res.collaterals[0].is_stale);
+        assert_eq!(100, res.collaterals[0].price_age);
+
+        // 130 - 100 = 30s old, within the window.
+        let res = query_market_state(&deps, Some(130)).unwrap();
+        assert!(!res.borrow_halted);
+        assert!(!res.collaterals[0].is_stale);
+        assert_eq!(30, res.collaterals[0].price_age);
+    }
+}
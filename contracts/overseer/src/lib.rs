@@ -0,0 +1,10 @@
+pub mod contract;
+pub mod msg;
+pub mod querier;
+pub mod state;
+
+#[cfg(test)]
+mod mock_querier;
+
+#[cfg(not(feature = "library"))]
+cosmwasm_std::create_entry_points!(contract);
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Coin, ContractResult, Extern, HumanAddr, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
+};
+
+use moneymarket::market::{BorrowerInfoResponse, QueryMsg as MarketQueryMsg};
+use moneymarket::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+
+/// Test double for the overseer's cross-contract queries: canned
+/// oracle prices keyed by base/quote pair and market loan amounts
+/// keyed by borrower, whichever contract address is queried.
+pub fn mock_dependencies(
+    canonical_length: usize,
+    contract_balance: &[Coin],
+) -> Extern<MockStorage, MockApi, WasmMockQuerier> {
+    let base = cosmwasm_std::testing::mock_dependencies(canonical_length, contract_balance);
+    Extern {
+        storage: base.storage,
+        api: base.api,
+        querier: WasmMockQuerier::new(contract_balance),
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier,
+    oracle_prices: HashMap<String, PriceResponse>,
+    borrow_amounts: HashMap<HumanAddr, Uint256>,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<()> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(contract_balance: &[Coin]) -> Self {
+        WasmMockQuerier {
+            base: MockQuerier::new(&[("contract", contract_balance)]),
+            oracle_prices: HashMap::new(),
+            borrow_amounts: HashMap::new(),
+        }
+    }
+
+    pub fn with_oracle_price(&mut self, base: &str, quote: &str, price: PriceResponse) {
+        self.oracle_prices
+            .insert(format!("{}-{}", base, quote), price);
+    }
+
+    pub fn with_loan_amount(&mut self, borrower: &HumanAddr, loan_amount: Uint256) {
+        self.borrow_amounts.insert(borrower.clone(), loan_amount);
+    }
+
+    fn handle_query(&self, request: &QueryRequest<()>) -> QuerierResult {
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => {
+                if let Ok(OracleQueryMsg::Price { base, quote }) = from_binary(msg) {
+                    let key = format!("{}-{}", base, quote);
+                    return match self.oracle_prices.get(&key) {
+                        Some(price) => SystemResult::Ok(ContractResult::Ok(to_binary(price).unwrap())),
+                        None => SystemResult::Ok(ContractResult::Err(format!(
+                            "no mock oracle price registered for {}",
+                            key
+                        ))),
+                    };
+                }
+
+                if let Ok(MarketQueryMsg::BorrowerInfo { borrower, .. }) = from_binary(msg) {
+                    let loan_amount = self
+                        .borrow_amounts
+                        .get(&borrower)
+                        .cloned()
+                        .unwrap_or_else(Uint256::zero);
+                    return SystemResult::Ok(ContractResult::Ok(
+                        to_binary(&BorrowerInfoResponse {
+                            borrower,
+                            loan_amount,
+                            interest_index: Decimal256::one(),
+                        })
+                        .unwrap(),
+                    ));
+                }
+
+                SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: "unmocked wasm smart query".to_string(),
+                })
+            }
+            other => self.base.handle_query(other),
+        }
+    }
+}
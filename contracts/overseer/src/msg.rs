@@ -5,6 +5,17 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::HumanAddr;
 use moneymarket::TokensHuman;
 
+/// Distinguishes "leave unchanged" (the field is omitted) from
+/// "clear back to `None`" when updating a field that is itself
+/// optional. An omitted field deserializes to `None` and is left
+/// untouched; `Some(Clear)` is required to null it back out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionalUpdate<T> {
+    Set(T),
+    Clear,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InitMsg {
@@ -16,6 +27,12 @@ pub struct InitMsg {
     pub market_contract: HumanAddr,
     /// Liquidation model contract address to compute liqudation amount
     pub liquidation_contract: HumanAddr,
+    /// Collector contract address to receive ANC purchase portion of the buffer
+    pub collector_contract: HumanAddr,
+    /// Liquidation queue contract address; when set, LiquidateCollateral
+    /// sells seized collateral into discount bid pools instead of
+    /// the liquidation_contract
+    pub liquidation_queue_contract: Option<HumanAddr>,
     /// The base denomination used when fetching oracle price,
     /// reward distribution, and borrow
     pub stable_denom: String,
@@ -30,8 +47,20 @@ pub struct InitMsg {
     pub target_deposit_rate: Decimal256,
     /// Ratio to be distributed from the interest buffer
     pub buffer_distribution_rate: Decimal256,
+    /// Ratio of the interest buffer sent to the collector contract
+    /// to buy back the governance token
+    pub anc_purchase_factor: Decimal256,
     /// Valid oracle price timeframe
     pub price_timeframe: u64,
+    /// Target borrow_amount / borrow_limit ratio a liquidated
+    /// position should be restored to
+    pub safe_ratio: Decimal256,
+    /// Maximum fraction of a borrower's liability that may be
+    /// repaid in a single liquidation call
+    pub close_factor: Decimal256,
+    /// Remaining liability below which the whole position is
+    /// liquidated instead of leaving dust
+    pub liquidation_threshold: Uint256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -46,11 +75,18 @@ pub enum HandleMsg {
         owner_addr: Option<HumanAddr>,
         oracle_contract: Option<HumanAddr>,
         liquidation_contract: Option<HumanAddr>,
+        collector_contract: Option<HumanAddr>,
+        // omit to leave unchanged, Some(Clear) to disable the queue
+        liquidation_queue_contract: Option<OptionalUpdate<HumanAddr>>,
         distribution_threshold: Option<Decimal256>,
         target_deposit_rate: Option<Decimal256>,
         buffer_distribution_rate: Option<Decimal256>,
+        anc_purchase_factor: Option<Decimal256>,
         epoch_period: Option<u64>,
         price_timeframe: Option<u64>,
+        safe_ratio: Option<Decimal256>,
+        close_factor: Option<Decimal256>,
+        liquidation_threshold: Option<Uint256>,
     },
 
     /// Create new custody contract for the given collateral token
@@ -58,12 +94,22 @@ pub enum HandleMsg {
         collateral_token: HumanAddr, // bAsset token contract
         custody_contract: HumanAddr, // bAsset custody contract
         ltv: Decimal256,             // Loan To Value ratio
+        collateral_ratio_multiplier: Option<Decimal256>, // per-collateral borrow limit multiplier
+        // highest liquidation queue bid pool to draw from; only
+        // meaningful once `liquidation_queue_contract` is configured
+        max_premium_rate: Option<Decimal256>,
     },
     /// Update registered whitelist info
     UpdateWhitelist {
         collateral_token: HumanAddr,         // bAsset token contract
         custody_contract: Option<HumanAddr>, // bAsset custody contract
         ltv: Option<Decimal256>,             // Loan To Value ratio
+        // per-collateral borrow limit multiplier; omit to leave unchanged,
+        // Some(Clear) to null it back out
+        collateral_ratio_multiplier: Option<OptionalUpdate<Decimal256>>,
+        // highest liquidation queue bid pool to draw from; omit to leave
+        // unchanged, Some(Clear) to null it back out
+        max_premium_rate: Option<OptionalUpdate<Decimal256>>,
     },
 
     /// Claims all staking rewards from the bAsset contracts
@@ -110,6 +156,10 @@ pub enum QueryMsg {
         borrower: HumanAddr,
         block_time: Option<u64>,
     },
+    LiquidationConfig {},
+    MarketState {
+        block_time: Option<u64>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -119,12 +169,18 @@ pub struct ConfigResponse {
     pub oracle_contract: HumanAddr,
     pub market_contract: HumanAddr,
     pub liquidation_contract: HumanAddr,
+    pub collector_contract: HumanAddr,
+    pub liquidation_queue_contract: Option<HumanAddr>,
     pub distribution_threshold: Decimal256,
     pub target_deposit_rate: Decimal256,
     pub buffer_distribution_rate: Decimal256,
+    pub anc_purchase_factor: Decimal256,
     pub stable_denom: String,
     pub epoch_period: u64,
     pub price_timeframe: u64,
+    pub safe_ratio: Decimal256,
+    pub close_factor: Decimal256,
+    pub liquidation_threshold: Uint256,
 }
 
 // We define a custom struct for each query response
@@ -133,6 +189,8 @@ pub struct WhitelistResponseElem {
     pub ltv: Decimal256,
     pub custody_contract: HumanAddr,
     pub collateral_token: HumanAddr,
+    pub collateral_ratio_multiplier: Option<Decimal256>,
+    pub max_premium_rate: Option<Decimal256>,
 }
 
 // We define a custom struct for each query response
@@ -162,8 +220,45 @@ pub struct DistributionParamsResponse {
     pub distribution_threshold: Decimal256,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationConfigResponse {
+    pub liquidation_queue_contract: Option<HumanAddr>,
+    pub collaterals: Vec<CollateralPremiumElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralPremiumElem {
+    pub collateral_token: HumanAddr,
+    pub max_premium_rate: Decimal256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowLimitResponse {
     pub borrower: HumanAddr,
     pub borrow_limit: Uint256,
+    pub collateral_limits: Vec<CollateralLimitElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketStateResponse {
+    pub borrow_halted: bool,
+    pub collaterals: Vec<CollateralPriceStateElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralPriceStateElem {
+    pub collateral_token: HumanAddr,
+    pub is_stale: bool,
+    pub price_age: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralLimitElem {
+    pub collateral_token: HumanAddr,
+    pub limit: Uint256,
 }
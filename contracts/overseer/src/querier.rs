@@ -0,0 +1,37 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    to_binary, Api, CanonicalAddr, Extern, HumanAddr, Querier, QueryRequest, StdResult, Storage,
+    WasmQuery,
+};
+
+use moneymarket::market::{BorrowerInfoResponse, QueryMsg as MarketQueryMsg};
+use moneymarket::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+
+pub fn query_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    oracle_contract: &HumanAddr,
+    base: String,
+    quote: String,
+) -> StdResult<PriceResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: oracle_contract.clone(),
+        msg: to_binary(&OracleQueryMsg::Price { base, quote })?,
+    }))
+}
+
+pub fn query_borrow_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    market_contract: &CanonicalAddr,
+    borrower: &HumanAddr,
+) -> StdResult<Uint256> {
+    let market_addr = deps.api.human_address(market_contract)?;
+    let info: BorrowerInfoResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: market_addr,
+        msg: to_binary(&MarketQueryMsg::BorrowerInfo {
+            borrower: borrower.clone(),
+            block_height: None,
+        })?,
+    }))?;
+
+    Ok(info.loan_amount)
+}
@@ -0,0 +1,93 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_storage::{bucket, bucket_read, singleton, singleton_read};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const KEY_CONFIG: &[u8] = b"config";
+const PREFIX_WHITELIST: &[u8] = b"whitelist";
+const PREFIX_COLLATERALS: &[u8] = b"collaterals";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner_addr: CanonicalAddr,
+    pub oracle_contract: CanonicalAddr,
+    pub market_contract: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub liquidation_queue_contract: Option<CanonicalAddr>,
+    pub stable_denom: String,
+    pub epoch_period: u64,
+    pub distribution_threshold: Decimal256,
+    pub target_deposit_rate: Decimal256,
+    pub buffer_distribution_rate: Decimal256,
+    pub anc_purchase_factor: Decimal256,
+    pub price_timeframe: u64,
+    pub safe_ratio: Decimal256,
+    pub close_factor: Decimal256,
+    pub liquidation_threshold: Uint256,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+/// Per-collateral whitelist entry, keyed by the collateral token's
+/// canonical address.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistElem {
+    pub custody_contract: CanonicalAddr,
+    pub ltv: Decimal256,
+    pub collateral_ratio_multiplier: Option<Decimal256>,
+    pub max_premium_rate: Option<Decimal256>,
+}
+
+pub fn store_whitelist_elem<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    elem: &WhitelistElem,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_WHITELIST).save(collateral_token.as_slice(), elem)
+}
+
+pub fn read_whitelist_elem<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<WhitelistElem> {
+    bucket_read(storage, PREFIX_WHITELIST).load(collateral_token.as_slice())
+}
+
+pub fn read_whitelist_elems<S: Storage>(
+    storage: &S,
+) -> StdResult<Vec<(CanonicalAddr, WhitelistElem)>> {
+    bucket_read(storage, PREFIX_WHITELIST)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+/// Amount of each collateral token a borrower currently has locked,
+/// keyed by the borrower's canonical address.
+pub fn store_collaterals<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    collaterals: &[(CanonicalAddr, Uint256)],
+) -> StdResult<()> {
+    bucket(storage, PREFIX_COLLATERALS).save(borrower.as_slice(), &collaterals.to_vec())
+}
+
+pub fn read_collaterals<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> Vec<(CanonicalAddr, Uint256)> {
+    bucket_read(storage, PREFIX_COLLATERALS)
+        .load(borrower.as_slice())
+        .unwrap_or_default()
+}